@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use web3::ethabi::Contract as AbiContract;
+use web3::types::Address;
+
+/// Describes which contract(s) and events a listener should watch,
+/// loaded from a JSON or TOML file so the tool isn't tied to one
+/// hardcoded ABI. The file path comes from `ABI_CONFIG_PATH`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AbiConfig {
+    /// Path to the contract's ABI JSON file.
+    pub abi_path: String,
+    /// Contract address(es) to watch for the configured events.
+    pub contract_addresses: Vec<Address>,
+    /// Names of events (as declared in the ABI) to subscribe to.
+    pub events: Vec<String>,
+    /// Where this config was loaded from, so callers can watch it for
+    /// changes and reload. Not part of the file itself.
+    #[serde(skip)]
+    pub config_path: String,
+}
+
+impl AbiConfig {
+    pub fn from_env() -> Result<Self> {
+        let path =
+            env::var("ABI_CONFIG_PATH").context("ABI_CONFIG_PATH must be set in .env file")?;
+        Self::from_file(&path)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ABI config at {}", path))?;
+
+        let mut config: AbiConfig = if path.ends_with(".toml") {
+            toml::from_str(&raw).context("Failed to parse ABI config as TOML")?
+        } else {
+            serde_json::from_str(&raw).context("Failed to parse ABI config as JSON")?
+        };
+        config.config_path = path.to_string();
+        Ok(config)
+    }
+
+    /// Load and parse the ABI file this config points at.
+    pub fn load_abi(&self) -> Result<AbiContract> {
+        let raw = fs::read_to_string(&self.abi_path)
+            .with_context(|| format!("Failed to read ABI file at {}", self.abi_path))?;
+        AbiContract::load(raw.as_bytes()).context("Failed to parse contract ABI")
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+
+const DEFAULT_CHECKPOINT_PATH: &str = "checkpoint.txt";
+
+/// Tracks the last fully-processed block on disk so a restart resumes
+/// backfill instead of re-scanning from scratch. Path is configurable
+/// via `CHECKPOINT_PATH`.
+pub struct Checkpoint {
+    path: String,
+}
+
+impl Checkpoint {
+    pub fn new() -> Self {
+        let path =
+            env::var("CHECKPOINT_PATH").unwrap_or_else(|_| DEFAULT_CHECKPOINT_PATH.to_string());
+        Checkpoint { path }
+    }
+
+    /// Returns the last block persisted, if any.
+    pub fn load(&self) -> Option<u64> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| contents.trim().parse().ok())
+    }
+
+    pub fn save(&self, block_number: u64) -> Result<()> {
+        fs::write(&self.path, block_number.to_string())
+            .with_context(|| format!("Failed to write checkpoint to {}", self.path))
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Collapse the burst of modify events most editors/filesystems produce
+// on a single save into one reload.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches `paths` for modifications and sends a notification on the
+/// returned channel each time one changes.
+pub fn watch(paths: Vec<PathBuf>) -> Result<mpsc::Receiver<()>> {
+    let (tx, rx) = mpsc::channel(1);
+    let (raw_tx, mut raw_rx) = mpsc::channel(16);
+
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<notify::Event>| {
+            if matches!(res, Ok(event) if event.kind.is_modify()) {
+                let _ = raw_tx.blocking_send(());
+            }
+        },
+        notify::Config::default(),
+    )?;
+
+    for path in &paths {
+        watcher.watch(path, RecursiveMode::NonRecursive)?;
+    }
+
+    tokio::spawn(async move {
+        // Keep the watcher alive for as long as this task runs.
+        let _watcher = watcher;
+        let mut last_sent = tokio::time::Instant::now()
+            .checked_sub(DEBOUNCE)
+            .unwrap_or_else(tokio::time::Instant::now);
+
+        while raw_rx.recv().await.is_some() {
+            if last_sent.elapsed() < DEBOUNCE {
+                continue;
+            }
+            last_sent = tokio::time::Instant::now();
+            if tx.send(()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(rx)
+}
@@ -1,128 +1,66 @@
 use anyhow::{Context, Result};
-use web3::transports::WebSocket;
-use web3::{
-    contract::{Contract, Options},
-    types::{Address, BlockId, Log, H160, H256, U256, U64},
-    Web3,
-};
+use serde::Serialize;
+use web3::ethabi::{Event as AbiEvent, RawLog};
+use web3::types::{Address, Log, H256, U256, U64};
 
-pub async fn get_contract(
-    eth: web3::api::Eth<WebSocket>,
-    address: Address,
-) -> Result<Contract<WebSocket>> {
-    let abi = r#" [
-        {
-            "anonymous": false,
-            "inputs": [
-                {
-                    "indexed": false,
-                    "internalType": "address",
-                    "name": "Sender",
-                    "type": "address"
-                }
-            ],
-            "name": "NumberUpdatedEvent",
-            "type": "event"
-        },
-        {
-            "inputs": [],
-            "name": "retrieve",
-            "outputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "",
-                    "type": "uint256"
-                }
-            ],
-            "stateMutability": "view",
-            "type": "function"
-        },
-        {
-            "inputs": [
-                {
-                    "internalType": "uint256",
-                    "name": "num",
-                    "type": "uint256"
-                }
-            ],
-            "name": "store",
-            "outputs": [],
-            "stateMutability": "nonpayable",
-            "type": "function"
-        }
-    ]"#;
+/// One decoded event parameter, indexed or not.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedParam {
+    pub name: String,
+    pub value: String,
+}
 
-    let contract = Contract::from_json(eth, address, abi.as_bytes())
-        .context("Error creating contract from ABI")?;
-    Ok(contract)
+/// A fully decoded event, detached from the ABI types used to produce
+/// it so it can be serialized and handed to any [`EventSink`].
+///
+/// [`EventSink`]: crate::sinks::EventSink
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedEvent {
+    pub tx_hash: H256,
+    pub block_number: U64,
+    pub log_index: Option<U256>,
+    pub contract_address: Address,
+    pub event_name: String,
+    pub params: Vec<DecodedParam>,
+    pub is_previous: bool,
 }
 
-pub async fn process_event(
-    web3: Web3<WebSocket>,
-    contract: Contract<WebSocket>,
-    log: Log,
-    is_previous: bool,
-) -> Result<()> {
+/// Decode `log` against the ABI-derived `event` definition. Replaces the
+/// old hardcoded "sender is topics[1]" assumption with a generic decode
+/// of whatever indexed/non-indexed params the event declares.
+pub fn decode_event(event: &AbiEvent, log: Log, is_previous: bool) -> Result<DecodedEvent> {
     let tx_hash = log
         .transaction_hash
         .context("Log should have transaction hash")?;
     let block_number = log.block_number.context("Log should have block number")?;
+    let contract_address = log.address;
+    let log_index = log.log_index;
 
-    // Extract sender address from the event data
-    let sender_address = if let Some(topics) = &log.topics.get(1) {
-        let mut bytes = [0u8; 32];
-        bytes.copy_from_slice(&topics.0);
-        H160::from_slice(&bytes[12..32]) // Convert the last 20 bytes to an address
-    } else {
-        let tx = web3
-            .eth()
-            .transaction(tx_hash.into())
-            .await
-            .context("Failed to fetch transaction")?
-            .context("Transaction should exist")?;
-        tx.from.unwrap_or_default()
+    let raw_log = RawLog {
+        topics: log.topics.clone(),
+        data: log.data.0.clone(),
     };
 
-    // Query the contract's state at the specific block number
-    let number: U256 = contract
-        .query(
-            "retrieve", // Function name in the contract
-            (),         // No input parameters
-            None,       // No specific sender address (use default)
-            Options::default(),
-            Some(BlockId::Number(block_number.into())), // Specify block number for query
-        )
-        .await
-        .context("Failed to query retrieve function")?;
-
-    // Print event information
-    display_information(tx_hash, block_number, sender_address, number, is_previous);
+    let decoded = event
+        .parse_log(raw_log)
+        .context("Failed to decode event log against ABI")?;
 
-    Ok(())
-}
+    let params = decoded
+        .params
+        .into_iter()
+        .map(|param| DecodedParam {
+            name: param.name,
+            value: param.value.to_string(),
+        })
+        .collect();
 
-fn display_information(
-    tx_hash: H256,
-    block_number: U64,
-    sender_address: H160,
-    number: U256,
-    is_previous: bool,
-) {
-    if is_previous {
-        // Print event information
-        println!("\n======= Event =======");
-        println!("Transaction: {:#x}", tx_hash);
-        println!("Block: {}", block_number);
-        println!("Sender: {:#x}", sender_address);
-        println!("New Value: {}", number);
-        println!("==========================\n");
-    } else {
-        // Print event information
-        println!("\n===== Event Detected =====");
-        println!("Transaction: {:#x}", tx_hash);
-        println!("Block: {}", block_number);
-        println!("Sender: {:#x}", sender_address);
-        println!("New Value: {}", number);
-        println!("==========================\n");
-    }
+    Ok(DecodedEvent {
+        tx_hash,
+        block_number,
+        log_index,
+        contract_address,
+        event_name: event.name.clone(),
+        params,
+        is_previous,
+    })
 }
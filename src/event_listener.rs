@@ -1,107 +1,509 @@
-use crate::contract::{get_contract, process_event};
-use crate::web3_client::Web3Client;
-use anyhow::Result;
+use crate::abi_config::AbiConfig;
+use crate::checkpoint::Checkpoint;
+use crate::config_watcher;
+use crate::contract::decode_event;
+use crate::sinks::EventSink;
+use crate::web3_client::{Web3Client, Web3Provider};
+use anyhow::{Context, Result};
 use futures::stream::StreamExt;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, timeout, Duration};
-use web3::types::Address;
+use tokio_util::sync::CancellationToken;
+use web3::ethabi::Event as AbiEvent;
+use web3::types::{Address, BlockNumber, FilterBuilder, Log, H256};
+
+// How often an HTTP connection polls `eth_getFilterChanges` for new logs.
+const HTTP_POLL_INTERVAL: Duration = Duration::from_millis(2000);
+
+// Backfill chunking: start conservative, grow back toward the max on
+// repeated success, and halve on a "range too large" style error.
+const INITIAL_CHUNK_SIZE: u64 = 500;
+const MAX_CHUNK_SIZE: u64 = 2000;
+const MIN_CHUNK_SIZE: u64 = 50;
+// Used when there's no checkpoint and no explicit BACKFILL_FROM_BLOCK.
+const DEFAULT_BACKFILL_DEPTH: u64 = 10_000;
+// How many times to retry a chunk after a transient RPC error (node
+// dropped mid-call, rate limited, etc.) before giving up on backfill
+// entirely. Each retry re-selects a connection from the pool, so a
+// failover to a healthy node happens automatically via `report_error`.
+const MAX_BACKFILL_RETRIES: u32 = 5;
+const BACKFILL_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// An independent live subscription for a single contract address,
+/// cancellable on its own so one failing address can't take the rest
+/// of the listener down with it.
+struct Subscription {
+    token: CancellationToken,
+    handle: JoinHandle<()>,
+}
 
 pub struct EventListener {
-    contract_address: Address,
     client: Web3Client,
+    abi_config: AbiConfig,
+    // Indexed by topic0 so an incoming log can be matched back to the
+    // ABI event definition that decodes it.
+    events: Arc<HashMap<H256, AbiEvent>>,
+    sinks: Arc<Vec<Box<dyn EventSink>>>,
+    subscriptions: HashMap<Address, Subscription>,
+    env_path: String,
+    node_urls: Vec<String>,
 }
 
 impl EventListener {
-    pub fn new(client: Web3Client, contract_address: Address) -> Self {
-        EventListener {
-            contract_address,
+    pub fn new(
+        client: Web3Client,
+        abi_config: AbiConfig,
+        node_urls: Vec<String>,
+        sinks: Vec<Box<dyn EventSink>>,
+    ) -> Result<Self> {
+        let events = Arc::new(Self::load_events(&abi_config)?);
+
+        Ok(EventListener {
             client,
+            abi_config,
+            events,
+            sinks: Arc::new(sinks),
+            subscriptions: HashMap::new(),
+            env_path: env::var("ENV_PATH").unwrap_or_else(|_| ".env".to_string()),
+            node_urls,
+        })
+    }
+
+    fn load_events(abi_config: &AbiConfig) -> Result<HashMap<H256, AbiEvent>> {
+        let abi = abi_config.load_abi()?;
+
+        let mut events = HashMap::new();
+        for name in &abi_config.events {
+            let event = abi
+                .event(name)
+                .with_context(|| format!("Event `{}` not found in ABI", name))?
+                .clone();
+            events.insert(event.signature(), event);
         }
+        Ok(events)
     }
 
-    pub async fn listen_for_events(&mut self) -> Result<()> {
-        loop {
-            match self.subscribe_and_listen().await {
-                Ok(_) => break, // Successfully finished listening
-                Err(e) => {
-                    eprintln!("⚠️ Event listener error: {}. Retrying in 5 seconds...", e);
-                    sleep(Duration::from_secs(5)).await;
-                    self.client.reconnect().await?;
+    /// Scan past logs in bounded chunks before handing off to the live
+    /// subscriptions, resuming from the last checkpointed block so a
+    /// restart doesn't re-scan everything.
+    pub async fn backfill(&mut self) -> Result<()> {
+        let checkpoint = Checkpoint::new();
+        let to_block = self.current_head().await?;
+        let mut from_block = match checkpoint.load() {
+            Some(last_processed) => last_processed + 1,
+            None => env::var("BACKFILL_FROM_BLOCK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(|| to_block.saturating_sub(DEFAULT_BACKFILL_DEPTH)),
+        };
+
+        if from_block > to_block {
+            println!("⏪ Nothing to backfill, checkpoint is up to date");
+            return Ok(());
+        }
+
+        println!(
+            "⏪ Backfilling events from block {} to {}",
+            from_block, to_block
+        );
+
+        let mut chunk_size = INITIAL_CHUNK_SIZE;
+        let mut retries = 0u32;
+
+        while from_block <= to_block {
+            let chunk_end = (from_block + chunk_size - 1).min(to_block);
+
+            let topics: Vec<H256> = self.events.keys().cloned().collect();
+            let filter = FilterBuilder::default()
+                .address(self.abi_config.contract_addresses.clone())
+                .topics(Some(topics), None, None, None)
+                .from_block(BlockNumber::Number(from_block.into()))
+                .to_block(BlockNumber::Number(chunk_end.into()))
+                .build();
+
+            match self.fetch_logs(filter).await {
+                Ok(logs) => {
+                    for log in logs {
+                        // A single undecodable log shouldn't take down a scan
+                        // of thousands of blocks; log it and keep going, same
+                        // as the live subscription path does for this class
+                        // of error.
+                        if let Err(e) = dispatch(&self.events, &self.sinks, log, true).await {
+                            eprintln!("⚠️ Failed to dispatch backfilled log: {}", e);
+                        }
+                    }
+                    checkpoint.save(chunk_end)?;
+                    from_block = chunk_end + 1;
+                    chunk_size = (chunk_size * 2).min(MAX_CHUNK_SIZE);
+                    retries = 0;
+                }
+                Err(e) if is_range_too_large(&e) => {
+                    chunk_size = (chunk_size / 2).max(MIN_CHUNK_SIZE);
+                    eprintln!(
+                        "Range {}-{} too large for node, shrinking chunk size to {}",
+                        from_block, chunk_end, chunk_size
+                    );
+                }
+                Err(e) if retries < MAX_BACKFILL_RETRIES => {
+                    retries += 1;
+                    eprintln!(
+                        "Transient error fetching logs {}-{}: {}. Retrying ({}/{})...",
+                        from_block, chunk_end, e, retries, MAX_BACKFILL_RETRIES
+                    );
+                    sleep(BACKFILL_RETRY_DELAY).await;
                 }
+                Err(e) => return Err(e),
+            }
+        }
+
+        println!("✅ Backfill complete, handing off to live subscriptions");
+        Ok(())
+    }
+
+    async fn current_head(&self) -> Result<u64> {
+        let (provider, node_url) = self.client.provider()?;
+        let result = match provider {
+            Web3Provider::Ws(web3) => web3.eth().block_number().await,
+            Web3Provider::Http(web3) => web3.eth().block_number().await,
+        };
+        match result {
+            Ok(block_number) => Ok(block_number.as_u64()),
+            Err(e) => {
+                self.client.report_error(&node_url);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn fetch_logs(&self, filter: web3::types::Filter) -> Result<Vec<Log>> {
+        let (provider, node_url) = self.client.provider()?;
+        let result = match provider {
+            Web3Provider::Ws(web3) => web3.eth().logs(filter).await,
+            Web3Provider::Http(web3) => web3.eth().logs(filter).await,
+        };
+        result.map_err(|e| {
+            let err = anyhow::Error::from(e);
+            // "Range too large" is the node rejecting the shape of this
+            // particular request, not a sign the connection itself is
+            // down — reporting it here would mark the connection
+            // unhealthy right before backfill's own retry (with a
+            // shrunk chunk) needs it, turning a routine chunk-size
+            // adjustment into "no healthy connection in pool".
+            if !is_range_too_large(&err) {
+                self.client.report_error(&node_url);
             }
+            err
+        })
+    }
+
+    /// Spawn a live subscription per contract address and then watch the
+    /// ABI config and `.env` files for changes, diffing them in as they
+    /// happen instead of requiring a restart.
+    pub async fn listen_for_events(mut self) -> Result<()> {
+        for address in self.abi_config.contract_addresses.clone() {
+            self.spawn_subscription(address);
         }
+
+        let mut watch_paths = vec![PathBuf::from(&self.abi_config.config_path)];
+        // Only watch the env file if it actually exists on disk — deployments
+        // that inject config via the real process environment (Docker/k8s/CI)
+        // never ship a literal `.env` file, and `notify::Watcher::watch` errors
+        // out on a missing path, which would otherwise abort startup here.
+        if Path::new(&self.env_path).exists() {
+            watch_paths.push(PathBuf::from(&self.env_path));
+        }
+        let mut reload_rx = config_watcher::watch(watch_paths)?;
+
+        while reload_rx.recv().await.is_some() {
+            if let Err(e) = self.reload().await {
+                eprintln!("⚠️ Failed to reload config: {}", e);
+            }
+        }
+
         Ok(())
     }
 
-    async fn subscribe_and_listen(&mut self) -> Result<()> {
-        let event_signature = "NumberUpdatedEvent(address)";
-        let event_signature_hash = web3::signing::keccak256(event_signature.as_bytes());
-
-        let filter = web3::types::FilterBuilder::default()
-            .address(vec![self.contract_address])
-            .topics(
-                Some(vec![web3::types::H256::from_slice(&event_signature_hash)]),
-                None,
-                None,
-                None,
-            )
-            .build();
-
-        // Retry subscription until successful
+    fn spawn_subscription(&mut self, address: Address) {
+        let token = CancellationToken::new();
+        let child_token = token.clone();
+        let client = self.client.clone();
+        let events = Arc::clone(&self.events);
+        let sinks = Arc::clone(&self.sinks);
+
+        let handle = tokio::spawn(async move {
+            run_subscription(client, events, sinks, address, child_token).await;
+        });
+
+        self.subscriptions.insert(address, Subscription { token, handle });
+    }
+
+    /// Reparse the ABI config and `.env`, then apply the diff: spawn
+    /// subscriptions for newly added addresses, cancel ones that were
+    /// removed, restart subscriptions whose watched event set changed,
+    /// and hot-swap the node pool if its URL set changed.
+    async fn reload(&mut self) -> Result<()> {
+        println!("🔁 Config changed, reloading...");
+
+        let new_config = AbiConfig::from_file(&self.abi_config.config_path)?;
+        let new_events = Arc::new(Self::load_events(&new_config)?);
+
+        let old_addresses: HashSet<Address> =
+            self.abi_config.contract_addresses.iter().cloned().collect();
+        let new_addresses: HashSet<Address> =
+            new_config.contract_addresses.iter().cloned().collect();
+
+        // Every subscription filters on the same global topic list (see
+        // `run_once`), so if that list changed, every still-watched
+        // address needs to be respawned to pick it up — each running
+        // task holds the `Arc<HashMap>` it was spawned with for life.
+        let old_topics: HashSet<H256> = self.events.keys().cloned().collect();
+        let new_topics: HashSet<H256> = new_events.keys().cloned().collect();
+        let events_changed = old_topics != new_topics;
+
+        for removed in old_addresses.difference(&new_addresses) {
+            if let Some(sub) = self.subscriptions.remove(removed) {
+                sub.token.cancel();
+                let _ = sub.handle.await;
+                println!("➖ Stopped watching {:#x}", removed);
+            }
+        }
+
+        if events_changed {
+            for address in old_addresses.intersection(&new_addresses) {
+                if let Some(sub) = self.subscriptions.remove(address) {
+                    sub.token.cancel();
+                    let _ = sub.handle.await;
+                }
+            }
+        }
+
+        self.events = new_events;
+        self.abi_config = new_config;
+
+        for address in new_addresses
+            .iter()
+            .filter(|a| !self.subscriptions.contains_key(a))
+            .cloned()
+            .collect::<Vec<_>>()
+        {
+            self.spawn_subscription(address);
+            if old_addresses.contains(&address) {
+                println!("🔁 Restarted subscription for {:#x} with updated event filter", address);
+            } else {
+                println!("➕ Watching new contract {:#x}", address);
+            }
+        }
+
+        let new_node_urls = read_node_urls(&self.env_path).unwrap_or_else(|_| self.node_urls.clone());
+        if new_node_urls != self.node_urls {
+            println!("🔄 Node URL set changed, updating pool...");
+            self.node_urls = new_node_urls.clone();
+            self.client.set_node_urls(&new_node_urls).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs as an independent task per contract address: subscribes (or
+/// polls, for HTTP-only nodes) until `token` is cancelled or the
+/// subscription itself gives up.
+async fn run_subscription(
+    client: Web3Client,
+    events: Arc<HashMap<H256, AbiEvent>>,
+    sinks: Arc<Vec<Box<dyn EventSink>>>,
+    address: Address,
+    token: CancellationToken,
+) {
+    loop {
+        if token.is_cancelled() {
+            return;
+        }
+
+        let (provider, node_url) = match client.provider() {
+            Ok(provider) => provider,
+            Err(e) => {
+                eprintln!(
+                    "⚠️ No healthy node available for {:#x}: {}. Retrying in 5 seconds...",
+                    address, e
+                );
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+
+        let result = tokio::select! {
+            _ = token.cancelled() => return,
+            result = run_once(provider, &events, &sinks, address) => result,
+        };
+
+        if let Err(e) = result {
+            client.report_error(&node_url);
+            eprintln!(
+                "⚠️ Subscription for {:#x} error: {}. Retrying in 5 seconds...",
+                address, e
+            );
+            sleep(Duration::from_secs(5)).await;
+        }
+    }
+}
+
+async fn run_once(
+    provider: Web3Provider,
+    events: &Arc<HashMap<H256, AbiEvent>>,
+    sinks: &Arc<Vec<Box<dyn EventSink>>>,
+    address: Address,
+) -> Result<()> {
+    let topics: Vec<H256> = events.keys().cloned().collect();
+    let filter = FilterBuilder::default()
+        .address(vec![address])
+        .topics(Some(topics), None, None, None)
+        .build();
+
+    match provider {
+        Web3Provider::Ws(web3) => {
+            subscribe_via_websocket(web3, events, sinks, filter, address).await
+        }
+        Web3Provider::Http(web3) => poll_via_http(web3, events, sinks, filter, address).await,
+    }
+}
+
+async fn subscribe_via_websocket(
+    web3: web3::Web3<web3::transports::WebSocket>,
+    events: &Arc<HashMap<H256, AbiEvent>>,
+    sinks: &Arc<Vec<Box<dyn EventSink>>>,
+    filter: web3::types::Filter,
+    address: Address,
+) -> Result<()> {
+    let mut sub = web3.eth_subscribe().subscribe_logs(filter).await?;
+    println!("📡 Listening for events on {:#x} (websocket)", address);
+
+    loop {
+        let log = timeout(Duration::from_secs(300), sub.next()).await;
+
+        match log {
+            Ok(Some(Ok(log))) => {
+                // A single undecodable/unexpected log shouldn't kill the
+                // whole subscription for this contract; log it and keep
+                // listening, same as the backfill path does.
+                if let Err(e) = dispatch(events, sinks, log, false).await {
+                    eprintln!("⚠️ Failed to dispatch log for {:#x}: {}", address, e);
+                }
+            }
+            Ok(Some(Err(err))) => {
+                return Err(anyhow::anyhow!(
+                    "Subscription for {:#x} failed: {:?}",
+                    address,
+                    err
+                ))
+            }
+            Ok(None) => {
+                println!("Stream closed for {:#x}", address);
+                return Ok(());
+            }
+            Err(_) => {
+                println!("Timeout waiting for event on {:#x}, retrying...", address);
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// HTTP-only nodes can't hold an `eth_subscribe` connection open, so
+/// fall back to `eth_newFilter` + `eth_getFilterChanges` polling. A
+/// filter is re-created whenever the node reports it has expired.
+async fn poll_via_http(
+    web3: web3::Web3<web3::transports::Http>,
+    events: &Arc<HashMap<H256, AbiEvent>>,
+    sinks: &Arc<Vec<Box<dyn EventSink>>>,
+    filter: web3::types::Filter,
+    address: Address,
+) -> Result<()> {
+    loop {
+        let live_filter = web3
+            .eth_filter()
+            .create_logs_filter(filter.clone())
+            .await
+            .context("Failed to create log filter")?;
+
+        println!("📡 Listening for events on {:#x} (http poll)", address);
+
         loop {
-            let web3 = self.client.web3();
-            let contract = get_contract(web3.eth(), self.contract_address).await?;
-
-            // Attempt to subscribe to the logs
-            match web3.eth_subscribe().subscribe_logs(filter.clone()).await {
-                Ok(mut sub) => {
-                    println!("📡 Listening for NumberUpdatedEvent...");
-
-                    // Process logs once subscribed
-                    loop {
-                        // Set a timeout for 30 seconds
-                        let event = timeout(Duration::from_secs(300), sub.next()).await;
-
-                        match event {
-                            Ok(Some(log)) => match log {
-                                Ok(log) => {
-                                    process_event(web3.clone(), contract.clone(), log).await?
-                                }
-                                Err(err) => {
-                                    eprintln!(
-                                        "⚠️ Error processing event: {:?}. Reconnecting...",
-                                        err
-                                    );
-                                    return Err(anyhow::anyhow!(
-                                        "Subscription failed, reconnecting..."
-                                    ));
-                                }
-                            },
-                            Ok(None) => {
-                                // This means the stream was closed, break the loop
-                                println!("Stream closed");
-                                break;
-                            }
-                            Err(_) => {
-                                // Timeout reached, reconnect or retry
-                                println!("Timeout waiting for event, trying again...");
-                                break;
-                            }
+            sleep(HTTP_POLL_INTERVAL).await;
+
+            match live_filter.poll().await {
+                Ok(Some(logs)) => {
+                    for log in logs {
+                        if let Err(e) = dispatch(events, sinks, log, false).await {
+                            eprintln!("⚠️ Failed to dispatch log for {:#x}: {}", address, e);
                         }
                     }
-                    // Reconnect and re-subscribe after failure
-                    println!("Reconnecting...");
-                    self.client.reconnect().await?;
                 }
-                Err(e) => {
-                    eprintln!(
-                        "⚠️ Failed to subscribe to logs: {}. Retrying in 5 seconds...",
-                        e
-                    );
-                    sleep(Duration::from_secs(5)).await;
-                    // Reconnect and re-subscribe after failure
-                    self.client.reconnect().await?;
+                Ok(None) => {}
+                Err(e) if is_filter_not_found(&e) => {
+                    eprintln!("Log filter for {:#x} expired, recreating...", address);
+                    break;
                 }
+                Err(e) => return Err(e.into()),
             }
         }
     }
 }
+
+/// Match a log back to the ABI event that declares it, decode it, and
+/// fan it out to every configured sink.
+async fn dispatch(
+    events: &HashMap<H256, AbiEvent>,
+    sinks: &[Box<dyn EventSink>],
+    log: Log,
+    is_previous: bool,
+) -> Result<()> {
+    let topic0 = log.topics.first().context("Log is missing topic0")?;
+    let event = events
+        .get(topic0)
+        .context("Received log for an unconfigured event")?;
+    let decoded = decode_event(event, log, is_previous)?;
+
+    for sink in sinks {
+        if let Err(e) = sink.emit(&decoded).await {
+            eprintln!("⚠️ Sink failed to emit event: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Minimal `.env` reader for the one key we need to hot-diff; we can't
+/// rely on the `dotenv` crate here since it refuses to override
+/// already-set process environment variables.
+fn read_node_urls(env_path: &str) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(env_path)
+        .with_context(|| format!("Failed to read {}", env_path))?;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("NODE_URLS=") {
+            return Ok(value.split(',').map(|s| s.trim().to_string()).collect());
+        }
+        if let Some(value) = line.strip_prefix("NODE_URL=") {
+            return Ok(vec![value.trim().to_string()]);
+        }
+    }
+
+    anyhow::bail!("NODE_URLS or NODE_URL not found in {}", env_path)
+}
+
+fn is_filter_not_found(err: &web3::Error) -> bool {
+    err.to_string().to_lowercase().contains("filter not found")
+}
+
+fn is_range_too_large(err: &anyhow::Error) -> bool {
+    let message = err.to_string().to_lowercase();
+    message.contains("query returned more than")
+        || message.contains("range too large")
+        || message.contains("block range")
+}
@@ -1,12 +1,15 @@
+mod abi_config;
+mod checkpoint;
+mod config_watcher;
 mod contract;
 mod event_listener;
+mod sinks;
 mod web3_client;
+use abi_config::AbiConfig;
 use anyhow::{Context, Result};
 use dotenv::dotenv;
 use event_listener::EventListener;
 use std::env;
-use std::str::FromStr;
-use web3::types::Address;
 use web3_client::Web3Client;
 
 #[tokio::main]
@@ -14,23 +17,36 @@ async fn main() -> Result<()> {
     // Load environment variables from .env file
     dotenv().ok();
 
-    // Get environment variables
-    let node_url = env::var("NODE_URL").context("NODE_URL must be set in .env file")?;
-    let contract_address =
-        env::var("CONTRACT_ADDRESS").context("CONTRACT_ADDRESS must be set in .env file")?;
+    // Get environment variables. NODE_URLS is a comma-separated list of
+    // nodes to pool for failover; NODE_URL is kept as a single-node
+    // fallback for existing setups.
+    let node_urls: Vec<String> = match env::var("NODE_URLS") {
+        Ok(urls) => urls.split(',').map(|s| s.trim().to_string()).collect(),
+        Err(_) => vec![
+            env::var("NODE_URL").context("NODE_URLS or NODE_URL must be set in .env file")?
+        ],
+    };
 
-    // Create Ethereum Client with WebSocket and retry logic
-    let mut client = Web3Client::new(&node_url);
+    // Create Ethereum Client pool with WebSocket and retry logic
+    let mut client = Web3Client::new(&node_urls);
     client.connect_with_retry().await?;
 
-    // Convert contract address string to Address
-    let contract_address =
-        Address::from_str(&contract_address).context("Invalid contract address format")?;
+    // Load which contract(s) and events to watch from the configured ABI file
+    let abi_config = AbiConfig::from_env()?;
 
-    println!("Monitoring contract at: {:#x}", contract_address);
+    println!(
+        "Monitoring {} contract(s) for events: {}",
+        abi_config.contract_addresses.len(),
+        abi_config.events.join(", ")
+    );
+
+    // Build the configured set of event sinks (stdout by default; see
+    // `sinks::from_env` for the full list).
+    let sinks = sinks::from_env()?;
 
     // Create EventListener instance
-    let mut listener = EventListener::new(client, contract_address);
+    let mut listener = EventListener::new(client, abi_config, node_urls, sinks)?;
+    listener.backfill().await?;
     listener.listen_for_events().await?;
 
     Ok(())
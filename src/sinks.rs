@@ -0,0 +1,131 @@
+use crate::contract::DecodedEvent;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Destination for decoded events. `dispatch` builds one
+/// [`DecodedEvent`] per log and fans it out to every configured sink, so
+/// adding a new downstream consumer only means adding an impl here.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    async fn emit(&self, event: &DecodedEvent) -> Result<()>;
+}
+
+/// Human-readable console output — the tool's original behavior.
+pub struct StdoutSink;
+
+#[async_trait]
+impl EventSink for StdoutSink {
+    async fn emit(&self, event: &DecodedEvent) -> Result<()> {
+        if event.is_previous {
+            println!("\n======= Event =======");
+        } else {
+            println!("\n===== Event Detected =====");
+        }
+        println!("Name: {}", event.event_name);
+        println!("Contract: {:#x}", event.contract_address);
+        println!("Transaction: {:#x}", event.tx_hash);
+        println!("Block: {}", event.block_number);
+        for param in &event.params {
+            println!("{}: {}", param.name, param.value);
+        }
+        println!("==========================\n");
+        Ok(())
+    }
+}
+
+/// Newline-delimited JSON, appended to a file (or written to stdout if
+/// no path is configured) for feeding into indexers or log pipelines.
+pub struct JsonLinesSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLinesSink {
+    pub fn to_file(path: &str) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {} for JSON-lines output", path))?;
+        Ok(JsonLinesSink {
+            writer: Mutex::new(Box::new(file)),
+        })
+    }
+
+    pub fn to_stdout() -> Self {
+        JsonLinesSink {
+            writer: Mutex::new(Box::new(std::io::stdout())),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for JsonLinesSink {
+    async fn emit(&self, event: &DecodedEvent) -> Result<()> {
+        let line = serde_json::to_string(event).context("Failed to serialize event to JSON")?;
+        let mut writer = self.writer.lock().expect("JSON-lines sink mutex poisoned");
+        writeln!(writer, "{}", line).context("Failed to write JSON-lines event")
+    }
+}
+
+/// POSTs each decoded event as JSON to a configured webhook URL.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new(url: String) -> Self {
+        WebhookSink {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    async fn emit(&self, event: &DecodedEvent) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(event)
+            .send()
+            .await
+            .context("Failed to POST event to webhook")?
+            .error_for_status()
+            .context("Webhook returned an error status")?;
+        Ok(())
+    }
+}
+
+/// Build the configured set of sinks from env vars:
+/// - `EVENT_SINKS` — comma-separated list of `stdout`, `jsonl`, `webhook` (default: `stdout`)
+/// - `EVENT_SINK_JSONL_PATH` — file path for the `jsonl` sink (stdout if unset)
+/// - `EVENT_SINK_WEBHOOK_URL` — required if `webhook` is selected
+pub fn from_env() -> Result<Vec<Box<dyn EventSink>>> {
+    let selected = std::env::var("EVENT_SINKS").unwrap_or_else(|_| "stdout".to_string());
+
+    let mut sinks: Vec<Box<dyn EventSink>> = Vec::new();
+    for name in selected.split(',').map(|s| s.trim()) {
+        match name {
+            "" => {}
+            "stdout" => sinks.push(Box::new(StdoutSink)),
+            "jsonl" => {
+                let sink = match std::env::var("EVENT_SINK_JSONL_PATH") {
+                    Ok(path) => JsonLinesSink::to_file(&path)?,
+                    Err(_) => JsonLinesSink::to_stdout(),
+                };
+                sinks.push(Box::new(sink));
+            }
+            "webhook" => {
+                let url = std::env::var("EVENT_SINK_WEBHOOK_URL")
+                    .context("EVENT_SINK_WEBHOOK_URL must be set to use the webhook sink")?;
+                sinks.push(Box::new(WebhookSink::new(url)));
+            }
+            other => anyhow::bail!("Unknown event sink `{}`", other),
+        }
+    }
+
+    Ok(sinks)
+}
@@ -1,62 +1,411 @@
 use anyhow::Result;
+use futures::stream::StreamExt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
-use web3::{transports::WebSocket, Web3};
+use tokio_util::sync::CancellationToken;
+use web3::{
+    transports::{Http, WebSocket},
+    Web3,
+};
 
+const MAX_CONNECT_ATTEMPTS: u32 = 5;
+// A connection whose head is more than this many blocks behind the best
+// known head is considered lagging and skipped during selection.
+const MAX_HEAD_LAG: u64 = 3;
+// How often an HTTP connection polls `eth_blockNumber` to track its head,
+// since it has no `newHeads` subscription to rely on.
+const HTTP_HEAD_POLL_INTERVAL: Duration = Duration::from_secs(3);
+// How long a connection's supervisor waits before trying the 5-attempt
+// backoff again after it gives up, so a node that's down for a while
+// doesn't get hammered with reconnect bursts forever.
+const RECONNECT_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// The underlying JSON-RPC transport for a connection. A node URL
+/// starting with `http(s)://` gets a plain `Http` transport (no
+/// subscriptions, used for polling-based log retrieval); anything else
+/// is assumed to be a websocket endpoint.
 #[derive(Clone)]
-pub struct Web3Client {
+pub enum Web3Provider {
+    Http(Web3<Http>),
+    Ws(Web3<WebSocket>),
+}
+
+impl Web3Provider {
+    async fn head(&self) -> Result<u64> {
+        let block_number = match self {
+            Web3Provider::Http(web3) => web3.eth().block_number().await?,
+            Web3Provider::Ws(web3) => web3.eth().block_number().await?,
+        };
+        Ok(block_number.as_u64())
+    }
+}
+
+fn is_http_url(node_url: &str) -> bool {
+    node_url.starts_with("http://") || node_url.starts_with("https://")
+}
+
+/// A single node in the pool: its endpoint, the current provider handle
+/// (if connected), the latest head block we've observed from it, and
+/// whether it is currently considered usable.
+struct Connection {
     node_url: String,
-    web3: Option<Web3<WebSocket>>,
+    provider: arc_swap::ArcSwap<Option<Web3Provider>>,
+    head: AtomicU64,
+    healthy: AtomicBool,
+    // Cancelled when this connection is dropped from the pool (e.g. a
+    // hot config reload removed its URL), so its supervisor task stops
+    // trying to keep it alive instead of running forever.
+    shutdown: CancellationToken,
 }
 
-impl Web3Client {
-    pub fn new(node_url: &str) -> Self {
-        Web3Client {
-            node_url: node_url.to_string(),
-            web3: None,
+impl Connection {
+    fn new(node_url: String) -> Self {
+        Connection {
+            node_url,
+            provider: arc_swap::ArcSwap::from_pointee(None),
+            head: AtomicU64::new(0),
+            healthy: AtomicBool::new(false),
+            shutdown: CancellationToken::new(),
         }
     }
 
-    pub async fn connect_with_retry(&mut self) -> Result<()> {
+    fn provider(&self) -> Option<Web3Provider> {
+        self.provider.load().as_ref().clone()
+    }
+
+    fn mark_unhealthy(&self) {
+        self.healthy.store(false, Ordering::SeqCst);
+    }
+
+    fn mark_healthy(&self) {
+        self.healthy.store(true, Ordering::SeqCst);
+    }
+
+    fn node_url(&self) -> &str {
+        &self.node_url
+    }
+
+    fn head(&self) -> u64 {
+        self.head.load(Ordering::SeqCst)
+    }
+
+    fn shut_down(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// Connect with the 5-attempt backoff, then spawn a supervisor task
+    /// that both keeps `head` up to date and reconnects with the same
+    /// backoff whenever the connection drops, for as long as this
+    /// connection stays in the pool.
+    async fn connect_with_retry(self: &Arc<Self>) -> Result<()> {
+        self.establish().await?;
+
+        let this = Arc::clone(self);
+        tokio::spawn(async move {
+            this.supervise().await;
+        });
+
+        Ok(())
+    }
+
+    /// The 5-attempt backoff itself, without the supervisor spawn. Used
+    /// both for the initial connect and for every reconnect afterwards.
+    async fn establish(&self) -> Result<()> {
         let mut attempts = 0;
         loop {
-            match WebSocket::new(&self.node_url).await {
-                Ok(ws) => {
-                    self.web3 = Some(Web3::new(ws));
+            let attempt = if is_http_url(&self.node_url) {
+                // `Http::new` only parses the URL and builds a client, so it
+                // never fails for an unreachable node on its own — probe with
+                // a cheap call so a dead HTTP endpoint still goes through the
+                // same backoff as a WebSocket connect failure instead of
+                // "succeeding" instantly and spinning unthrottled.
+                match Http::new(&self.node_url) {
+                    Ok(http) => {
+                        let provider = Web3Provider::Http(Web3::new(http));
+                        provider.head().await.map(|_| provider)
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            } else {
+                match WebSocket::new(&self.node_url).await {
+                    Ok(ws) => Ok(Web3Provider::Ws(Web3::new(ws))),
+                    Err(e) => Err(e.into()),
+                }
+            };
+
+            match attempt {
+                Ok(provider) => {
+                    self.provider.store(Arc::new(Some(provider)));
+                    self.healthy.store(true, Ordering::SeqCst);
                     println!("Connected to Ethereum node at: {}", self.node_url);
-                    break;
+                    return Ok(());
                 }
                 Err(e) => {
                     attempts += 1;
                     eprintln!(
-                        "Failed to connect to WebSocket: {}. Retrying {}/5...",
-                        e, attempts
+                        "Failed to connect to node ({}): {}. Retrying {}/{}...",
+                        self.node_url, e, attempts, MAX_CONNECT_ATTEMPTS
                     );
-                    if attempts >= 5 {
-                        return Err(anyhow::anyhow!("Failed to connect after 5 attempts").into());
+                    if attempts >= MAX_CONNECT_ATTEMPTS {
+                        return Err(anyhow::anyhow!(
+                            "Failed to connect to {} after {} attempts",
+                            self.node_url,
+                            MAX_CONNECT_ATTEMPTS
+                        ));
                     }
                     sleep(Duration::from_secs(5)).await;
                 }
             }
         }
+    }
+
+    /// Keep this connection alive for as long as it's in the pool: track
+    /// its head until the subscription/poll loop gives up (the node
+    /// dropped, rate-limited us, etc.), then reconnect with the same
+    /// backoff and start tracking again. This is what lets a node that
+    /// drops mid-run heal itself instead of being abandoned forever.
+    async fn supervise(self: Arc<Self>) {
+        loop {
+            self.track_head().await;
+
+            if self.shutdown.is_cancelled() {
+                return;
+            }
+
+            while let Err(e) = self.establish().await {
+                if self.shutdown.is_cancelled() {
+                    return;
+                }
+                eprintln!(
+                    "Giving up reconnecting to {} for now: {}. Retrying in {:?}...",
+                    self.node_url, e, RECONNECT_RETRY_DELAY
+                );
+                sleep(RECONNECT_RETRY_DELAY).await;
+            }
+        }
+    }
+
+    async fn track_head(&self) {
+        let Some(provider) = self.provider() else {
+            self.mark_unhealthy();
+            return;
+        };
+
+        match provider {
+            Web3Provider::Ws(web3) => self.track_head_via_subscription(web3).await,
+            Web3Provider::Http(_) => self.track_head_via_polling(provider).await,
+        }
+    }
+
+    /// Subscribe to `newHeads` and record the latest block number until
+    /// the subscription dies or `shutdown` fires, marking the connection
+    /// unhealthy either way; `supervise` reconnects us if it wasn't a
+    /// deliberate shutdown. Each head we actually receive marks the
+    /// connection healthy again, so an unrelated RPC failure reported via
+    /// `Web3Client::report_error` doesn't permanently exclude a
+    /// connection whose head subscription is still ticking along fine.
+    async fn track_head_via_subscription(&self, web3: Web3<WebSocket>) {
+        let mut sub = match web3.eth_subscribe().subscribe_new_heads().await {
+            Ok(sub) => sub,
+            Err(e) => {
+                eprintln!(
+                    "Failed to subscribe to newHeads on {}: {}",
+                    self.node_url, e
+                );
+                self.mark_unhealthy();
+                return;
+            }
+        };
+
+        loop {
+            let head = tokio::select! {
+                _ = self.shutdown.cancelled() => break,
+                head = sub.next() => head,
+            };
+
+            match head {
+                Some(Ok(header)) => {
+                    if let Some(number) = header.number {
+                        self.head.store(number.as_u64(), Ordering::SeqCst);
+                    }
+                    self.mark_healthy();
+                }
+                Some(Err(e)) => {
+                    eprintln!("newHeads subscription error on {}: {}", self.node_url, e);
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        self.mark_unhealthy();
+    }
+
+    /// HTTP has no subscriptions, so just poll `eth_blockNumber` on an
+    /// interval for as long as the provider stays reachable and
+    /// `shutdown` hasn't fired. Each successful poll marks the connection
+    /// healthy again, for the same reason as `track_head_via_subscription`.
+    async fn track_head_via_polling(&self, provider: Web3Provider) {
+        loop {
+            match provider.head().await {
+                Ok(block_number) => {
+                    self.head.store(block_number, Ordering::SeqCst);
+                    self.mark_healthy();
+                }
+                Err(e) => {
+                    eprintln!("Failed to poll head on {}: {}", self.node_url, e);
+                    self.mark_unhealthy();
+                    return;
+                }
+            }
+
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return,
+                _ = sleep(HTTP_HEAD_POLL_INTERVAL) => {}
+            }
+        }
+    }
+}
+
+/// Pool of node connections with automatic failover. `web3()` hands back
+/// the connection with the best healthy head (within `MAX_HEAD_LAG`
+/// blocks of the highest observed head across the pool), so a single
+/// flaky endpoint no longer stalls the whole listener. The connection
+/// set itself is hot-swappable via [`Web3Client::set_node_urls`], so a
+/// `Web3Client` clone handed to a long-running subscription task picks
+/// up pool membership changes made through any other clone.
+#[derive(Clone)]
+pub struct Web3Client {
+    connections: Arc<arc_swap::ArcSwap<Vec<Arc<Connection>>>>,
+}
+
+impl Web3Client {
+    pub fn new(node_urls: &[String]) -> Self {
+        Web3Client {
+            connections: Arc::new(arc_swap::ArcSwap::from_pointee(build_connections(
+                node_urls,
+            ))),
+        }
+    }
+
+    pub async fn connect_with_retry(&mut self) -> Result<()> {
+        self.connect_all().await
+    }
+
+    async fn connect_all(&self) -> Result<()> {
+        let mut last_err = None;
+        let mut connected = 0;
+
+        for conn in self.connections.load().iter() {
+            if conn.provider().is_some() {
+                connected += 1;
+                continue;
+            }
+            match conn.connect_with_retry().await {
+                Ok(()) => connected += 1,
+                Err(e) => {
+                    eprintln!("Giving up on node {}: {}", conn.node_url(), e);
+                    last_err = Some(e);
+                }
+            }
+        }
+
+        if connected == 0 {
+            return Err(last_err.unwrap_or_else(|| anyhow::anyhow!("No node URLs configured")));
+        }
+
         Ok(())
     }
 
-    pub async fn reconnect(&mut self) -> Result<()> {
-        println!("🔄 Attempting to reconnect...");
-        self.connect_with_retry().await
+    /// Replace the pool's node URL set: URLs that are already in the
+    /// pool keep their existing connection (and its head-tracking
+    /// supervisor) untouched, new URLs get a fresh connection, and URLs
+    /// no longer present are shut down and dropped. Connects every node
+    /// in the new set that isn't already connected before returning.
+    pub async fn set_node_urls(&self, node_urls: &[String]) -> Result<()> {
+        let current = self.connections.load_full();
+
+        let next: Vec<Arc<Connection>> = node_urls
+            .iter()
+            .map(|url| match current.iter().find(|c| c.node_url() == url) {
+                Some(existing) => Arc::clone(existing),
+                None => Arc::new(Connection::new(url.clone())),
+            })
+            .collect();
+
+        for dropped in current
+            .iter()
+            .filter(|c| !node_urls.iter().any(|url| url == c.node_url()))
+        {
+            dropped.shut_down();
+            println!("➖ Dropped node {} from the pool", dropped.node_url());
+        }
+
+        self.connections.store(Arc::new(next));
+        self.connect_all().await
     }
 
-    // pub async fn ping(&self) -> Result<()> {
-    //     let web3 = self.web3().clone();
-    //     // Send a lightweight eth_block_number call to check the connection
-    //     match web3.eth().block_number().await {
-    //         Ok(_) => Ok(()), // Connection is alive
-    //         Err(e) => Err(anyhow::anyhow!("Ping failed: {}", e)),
-    //     }
-    // }
+    /// Pick the best connection: healthy, connected, and with a head
+    /// within `MAX_HEAD_LAG` of the highest head observed in the pool.
+    /// Returns `None` if no connection in the pool currently qualifies.
+    fn select_connection(&self) -> Option<Arc<Connection>> {
+        let connections = self.connections.load();
+        let best_head = connections.iter().map(|c| c.head()).max().unwrap_or(0);
 
-    pub fn web3(&self) -> Web3<WebSocket> {
-        self.web3.clone().expect("Failed to get web3 context")
+        connections
+            .iter()
+            .filter(|c| c.healthy.load(Ordering::SeqCst) && c.provider().is_some())
+            .filter(|c| best_head.saturating_sub(c.head()) <= MAX_HEAD_LAG)
+            .max_by_key(|c| c.head())
+            .cloned()
     }
+
+    /// Return the provider of the best connection, whichever transport
+    /// it happens to use, along with the node URL it came from so the
+    /// caller can hand it back to [`Web3Client::report_error`] if the
+    /// call made with it fails. Errors (rather than panics) if every
+    /// connection in the pool is currently unhealthy or unconnected —
+    /// each connection's own supervisor is already trying to reconnect
+    /// it in the background, so callers just need to surface the
+    /// failure and retry on their own schedule.
+    pub fn provider(&self) -> Result<(Web3Provider, String)> {
+        let conn = self
+            .select_connection()
+            .ok_or_else(|| anyhow::anyhow!("No healthy connection in pool"))?;
+
+        match conn.provider() {
+            Some(provider) => Ok((provider, conn.node_url().to_string())),
+            None => {
+                conn.mark_unhealthy();
+                Err(anyhow::anyhow!(
+                    "Selected connection {} has no provider",
+                    conn.node_url()
+                ))
+            }
+        }
+    }
+
+    /// Report that a call made against `node_url` (as returned by
+    /// [`Web3Client::provider`]) failed, so the
+    /// connection is marked unhealthy and `select_connection` fails over
+    /// to the next-best node on the following call.
+    pub fn report_error(&self, node_url: &str) {
+        if let Some(conn) = self
+            .connections
+            .load()
+            .iter()
+            .find(|c| c.node_url() == node_url)
+        {
+            conn.mark_unhealthy();
+        }
+    }
+}
+
+fn build_connections(node_urls: &[String]) -> Vec<Arc<Connection>> {
+    node_urls
+        .iter()
+        .map(|url| Arc::new(Connection::new(url.clone())))
+        .collect()
 }